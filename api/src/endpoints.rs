@@ -21,16 +21,50 @@
 //   }
 // }
 
+use std::io::Read;
 use std::sync::Arc;
 use std::thread;
 
-use core::core::Output;
+use hyper::Client;
+use hyper::status::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json;
+
+use core::core::{Block, Output};
 use core::core::hash::Hash;
 use chain::{self, Tip};
 use rest::*;
 use secp::pedersen::Commitment;
 use util;
 
+pub use chain::Tip as ChainTip;
+
+/// Current protocol/version string, reported by `/v1/status`.
+const PROTOCOL_VERSION: &'static str = "0.1";
+
+/// Performs a simple HTTP GET against `url` and deserializes the JSON body
+/// into `T`. Factored out so the various `client` subcommands can talk to a
+/// node's REST API without each reimplementing request/response handling.
+pub fn get<T>(url: &str) -> Result<T, Error>
+	where T: Deserialize
+{
+	let client = Client::new();
+	let mut res = client.get(url)
+		.send()
+		.map_err(|e| Error::Internal(format!("Error contacting {}: {}", url, e)))?;
+
+	if res.status != StatusCode::Ok {
+		return Err(Error::Internal(format!("Unsuccessful response from {}: {}", url, res.status)));
+	}
+
+	let mut body = String::new();
+	res.read_to_string(&mut body)
+		.map_err(|e| Error::Internal(format!("Error reading response from {}: {}", url, e)))?;
+
+	serde_json::from_str(&body)
+		.map_err(|e| Error::Internal(format!("Error parsing response from {}: {}", url, e)))
+}
+
 /// ApiEndpoint implementation for the blockchain. Exposes the current chain
 /// state as a simple JSON object.
 #[derive(Clone)]
@@ -80,6 +114,99 @@ impl ApiEndpoint for OutputApi {
 	}
 }
 
+/// Identifies a block either by its hash or by its height, as accepted by
+/// `BlockApi`'s `{id}` path segment.
+enum BlockId {
+	Hash(Hash),
+	Height(u64),
+}
+
+/// Parses a `BlockApi` id: a 64-character hex block hash, or a decimal
+/// height.
+fn parse_block_id(id: &str) -> Result<BlockId, Error> {
+	if id.len() == 64 {
+		let bytes = util::from_hex(id.to_string())
+			.map_err(|_| Error::Argument(format!("Not a valid block hash: {}", id)))?;
+		return Ok(BlockId::Hash(Hash::from_vec(bytes)));
+	}
+	id.parse::<u64>()
+		.map(BlockId::Height)
+		.map_err(|_| Error::Argument(format!("{} is not a valid block hash or height", id)))
+}
+
+/// ApiEndpoint implementation for full blocks, addressable either by hash
+/// or by height.
+#[derive(Clone)]
+pub struct BlockApi {
+	/// data store access
+	chain_store: Arc<chain::ChainStore>,
+}
+
+impl ApiEndpoint for BlockApi {
+	type ID = String;
+	type T = Block;
+	type OP_IN = ();
+	type OP_OUT = ();
+
+	fn operations(&self) -> Vec<Operation> {
+		vec![Operation::Get]
+	}
+
+	fn get(&self, id: String) -> ApiResult<Block> {
+		debug!("GET block {}", id);
+		let hash = match parse_block_id(&id)? {
+			BlockId::Hash(hash) => hash,
+			BlockId::Height(height) => {
+				let header = self.chain_store
+					.get_header_by_height(height)
+					.map_err(|e| Error::Internal(e.to_string()))?;
+				header.hash()
+			}
+		};
+		self.chain_store.get_block(&hash).map_err(|e| Error::Internal(e.to_string()))
+	}
+}
+
+/// Aggregated node status, combining the chain tip and the protocol/version
+/// string into a single response so monitoring tools and the `client` CLI
+/// don't need to query several endpoints to get a full picture.
+///
+/// A `peer_count` field belongs here too, but reporting it needs a handle
+/// to the running `p2p::Peers` registry, which only the server startup in
+/// the `grin` crate has. Adding it would mean changing `start_rest_apis`'s
+/// signature without being able to update that (out-of-crate) call site in
+/// the same change, so it's left out until the two can land together.
+#[derive(Clone, Serialize)]
+pub struct Status {
+	tip: Tip,
+	version: String,
+}
+
+/// ApiEndpoint implementation for the aggregated node status.
+#[derive(Clone)]
+pub struct StatusApi {
+	chain_store: Arc<chain::ChainStore>,
+}
+
+impl ApiEndpoint for StatusApi {
+	type ID = String;
+	type T = Status;
+	type OP_IN = ();
+	type OP_OUT = ();
+
+	fn operations(&self) -> Vec<Operation> {
+		vec![Operation::Get]
+	}
+
+	fn get(&self, _: String) -> ApiResult<Status> {
+		let tip = self.chain_store.head().map_err(|e| Error::Internal(e.to_string()))?;
+		Ok(Status {
+			tip: tip,
+			version: PROTOCOL_VERSION.to_string(),
+		})
+	}
+}
+
 /// Start all server REST APIs. Just register all of them on a ApiServer
 /// instance and runs the corresponding HTTP server.
 pub fn start_rest_apis(addr: String, chain_store: Arc<chain::ChainStore>) {
@@ -90,6 +217,10 @@ pub fn start_rest_apis(addr: String, chain_store: Arc<chain::ChainStore>) {
 		                       ChainApi { chain_store: chain_store.clone() });
 		apis.register_endpoint("/chain/output".to_string(),
 		                       OutputApi { chain_store: chain_store.clone() });
+		apis.register_endpoint("/chain/block".to_string(),
+		                       BlockApi { chain_store: chain_store.clone() });
+		apis.register_endpoint("/status".to_string(),
+		                       StatusApi { chain_store: chain_store.clone() });
 		apis.start(&addr[..]).unwrap_or_else(|e| {
 			error!("Failed to start API HTTP server: {}.", e);
 		});
@@ -0,0 +1,561 @@
+// Copyright 2016 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Main for building the binary of a Grin peer-to-peer node.
+
+extern crate clap;
+extern crate daemonize;
+#[macro_use]
+extern crate log;
+extern crate env_logger;
+extern crate serde;
+extern crate serde_json;
+extern crate rand;
+extern crate sha2;
+extern crate hmac;
+extern crate pbkdf2;
+extern crate libc;
+
+extern crate grin_api as api;
+extern crate grin_chain as chain;
+extern crate grin_core as core;
+extern crate grin_grin as grin;
+extern crate grin_wallet as wallet;
+extern crate secp256k1zkp as secp;
+
+mod mnemonic;
+mod payment_request;
+
+const GRIN_HOME: &'static str = ".grin";
+const PID_FILE: &'static str = "/tmp/grin.pid";
+const WALLET_RECEIVE_ADDR: &'static str = "127.0.0.1:13416";
+
+use std::env;
+use std::thread;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::fs::File;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{Arg, App, SubCommand, ArgMatches};
+use daemonize::Daemonize;
+
+use secp::Secp256k1;
+use secp::key::PublicKey;
+
+fn main() {
+	env_logger::init().unwrap();
+
+	let args = App::new("Grin")
+    .version("0.1")
+    .author("The Grin Team")
+    .about("Lightweight implementation of the MimbleWimble protocol.")
+
+    // specification of all the server commands and options
+    .subcommand(SubCommand::with_name("server")
+                .about("Control the Grin server")
+                .arg(Arg::with_name("port")
+                     .short("p")
+                     .long("port")
+                     .help("Port to start the server on")
+                     .takes_value(true))
+                .arg(Arg::with_name("seed")
+                     .short("s")
+                     .long("seed")
+                     .help("Override seed node(s) to connect to")
+                     .takes_value(true)
+                     .multiple(true))
+                .arg(Arg::with_name("mine")
+                     .short("m")
+                     .long("mine")
+                     .help("Starts the debugging mining loop"))
+                .arg(Arg::with_name("config")
+                     .short("c")
+                     .long("config")
+                     .value_name("FILE.json")
+                     .help("Sets a custom json configuration file")
+                     .takes_value(true))
+                .subcommand(SubCommand::with_name("start")
+                            .about("Start the Grin server as a daemon"))
+                .subcommand(SubCommand::with_name("stop")
+                            .about("Stop the Grin server daemon"))
+                .subcommand(SubCommand::with_name("run")
+                            .about("Run the Grin server in this console"))
+                .subcommand(SubCommand::with_name("export")
+                            .about("Export the full blockchain to a file for backup")
+                            .arg(Arg::with_name("file")
+                                 .help("File to export the blockchain to")
+                                 .required(true)
+                                 .index(1)))
+                .subcommand(SubCommand::with_name("import")
+                            .about("Import a previously exported blockchain from a file")
+                            .arg(Arg::with_name("file")
+                                 .help("File to import the blockchain from")
+                                 .required(true)
+                                 .index(1)))
+                .subcommand(SubCommand::with_name("config")
+                            .about("Write a fully-populated default configuration to ~/.grin")))
+
+    // specification of all the client commands and options
+    .subcommand(SubCommand::with_name("client")
+                .about("Communicates with the Grin server")
+                .subcommand(SubCommand::with_name("status")
+                            .about("current status of the Grin chain")
+                            .arg(Arg::with_name("json")
+                                 .long("json")
+                                 .help("Print the raw JSON response instead of a human-readable summary"))))
+
+    // specification of the wallet commands and options
+    .subcommand(SubCommand::with_name("wallet")
+                .about("Wallet software for Grin")
+                .arg(Arg::with_name("mnemonic")
+                     .short("m")
+                     .long("mnemonic")
+                     .help("BIP39 mnemonic phrase used to derive the wallet's private key seed")
+                     .takes_value(true))
+                .arg(Arg::with_name("passphrase")
+                     .long("passphrase")
+                     .help("Optional extra passphrase to protect the mnemonic")
+                     .takes_value(true))
+                .subcommand(SubCommand::with_name("init")
+                            .about("Generate a new BIP39 mnemonic seed phrase")
+                            .arg(Arg::with_name("entropy")
+                                 .short("e")
+                                 .long("entropy")
+                                 .help("Entropy length in bits (128, 160, 192, 224 or 256)")
+                                 .takes_value(true)))
+                .subcommand(SubCommand::with_name("receive")
+                            .about("Run the wallet in receiving mode")
+                            .subcommand(SubCommand::with_name("request")
+                                        .about("Generate a payment-request string for the given amount")
+                                        .arg(Arg::with_name("amount")
+                                             .help("Amount to request in the smallest denomination")
+                                             .required(true)
+                                             .index(1))))
+                .subcommand(SubCommand::with_name("send")
+                            .about("Builds a transaction to send someone some coins. By default, the transaction will just be printed to stdout. If a destination or payment-request string is provided, the command will attempt to contact the receiver and send the transaction directly.")
+                            .arg(Arg::with_name("amount")
+                                 .help("Amount to send in the smallest denomination, not needed when a payment request is given")
+                                 .index(1))
+                            .arg(Arg::with_name("dest")
+                                 .help("Send the transaction to the provided server, or to the endpoint encoded in a payment-request string")
+                                 .short("d")
+                                 .long("dest")
+                                 .takes_value(true))))
+    .get_matches();
+
+	match args.subcommand() {
+		// server commands and options
+		("server", Some(server_args)) => {
+			server_command(server_args);
+		}
+
+		// client commands and options
+		("client", Some(client_args)) => {
+			match client_args.subcommand() {
+				("status", Some(status_args)) => {
+					client_status_command(status_args);
+				}
+				_ => panic!("Unknown client command, use 'grin help client' for details"),
+			}
+		}
+
+		// client commands and options
+		("wallet", Some(wallet_args)) => {
+			wallet_command(wallet_args);
+		}
+
+		_ => println!("Unknown command, use 'grin help' for a list of all commands"),
+	}
+}
+
+/// Handles the server part of the command line, mostly running, starting and
+/// stopping the Grin blockchain server. Processes all the command line
+/// arguments
+/// to build a proper configuration and runs Grin with that configuration.
+fn server_command(server_args: &ArgMatches) {
+	info!("Starting the Grin server...");
+
+	// configuration wrangling
+	let mut server_config = read_config();
+	if let Some(port) = server_args.value_of("port") {
+		server_config.p2p_config.port = port.parse().unwrap();
+	}
+	if server_args.is_present("mine") {
+		server_config.mining_config.enable_mining = true;
+	}
+	if let Some(seeds) = server_args.values_of("seed") {
+		server_config.seeding_type = grin::Seeding::List(seeds.map(|s| s.to_string()).collect());
+	}
+
+	// start the server in the different run modes (interactive or daemon)
+	match server_args.subcommand() {
+		("run", _) => {
+			grin::Server::start(server_config).unwrap();
+			loop {
+				thread::sleep(Duration::from_secs(60));
+			}
+		}
+		("start", _) => {
+			let daemonize = Daemonize::new()
+				.pid_file(PID_FILE)
+				.chown_pid_file(true)
+				.privileged_action(move || {
+					grin::Server::start(server_config.clone()).unwrap();
+					loop {
+						thread::sleep(Duration::from_secs(60));
+					}
+				});
+			match daemonize.start() {
+				Ok(_) => info!("Grin server succesfully started."),
+				Err(e) => error!("Error starting: {}", e),
+			}
+		}
+		("stop", _) => stop_daemon(),
+		("export", Some(export_args)) => {
+			let file = export_args.value_of("file").expect("Export file path required.");
+			export_chain(&server_config, file);
+		}
+		("import", Some(import_args)) => {
+			let file = import_args.value_of("file").expect("Import file path required.");
+			import_chain(&server_config, file);
+		}
+		("config", _) => write_default_config(),
+		_ => panic!("Unknown server command, use 'grin help server' for details"),
+	}
+}
+
+/// How long to wait for a graceful SIGTERM shutdown before escalating to
+/// SIGKILL. The server can have a large sumtree to flush, so this is
+/// generous; `stop_daemon` only gives up after neither signal works.
+const STOP_GRACEFUL_TIMEOUT_MS: u64 = 30_000;
+const STOP_POLL_INTERVAL_MS: u64 = 250;
+
+/// Stops the Grin server daemon started by `server start`: reads the pid
+/// written by `Daemonize`, sends it SIGTERM, waits up to
+/// `STOP_GRACEFUL_TIMEOUT_MS` for it to exit, escalates to SIGKILL if it
+/// hasn't, and cleans up the pid file once the process is confirmed gone.
+fn stop_daemon() {
+	let mut pid_file = match File::open(PID_FILE) {
+		Ok(f) => f,
+		Err(_) => {
+			println!("No Grin daemon appears to be running ({} not found).", PID_FILE);
+			return;
+		}
+	};
+	let mut pid_str = String::new();
+	pid_file.read_to_string(&mut pid_str).expect("Could not read pid file.");
+	let pid: libc::pid_t = pid_str.trim().parse().expect("Pid file does not contain a valid pid.");
+
+	if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+		println!("No running Grin daemon found for pid {}.", pid);
+		let _ = fs::remove_file(PID_FILE);
+		return;
+	}
+
+	let polls = STOP_GRACEFUL_TIMEOUT_MS / STOP_POLL_INTERVAL_MS;
+	for _ in 0..polls {
+		thread::sleep(Duration::from_millis(STOP_POLL_INTERVAL_MS));
+		if unsafe { libc::kill(pid, 0) } != 0 {
+			let _ = fs::remove_file(PID_FILE);
+			println!("Grin daemon (pid {}) stopped.", pid);
+			return;
+		}
+	}
+
+	error!("Grin daemon (pid {}) did not stop within {}ms of SIGTERM, sending SIGKILL.",
+	       pid, STOP_GRACEFUL_TIMEOUT_MS);
+	if unsafe { libc::kill(pid, libc::SIGKILL) } != 0 {
+		// Process exited between our last poll and here; nothing left to kill.
+		let _ = fs::remove_file(PID_FILE);
+		println!("Grin daemon (pid {}) stopped.", pid);
+		return;
+	}
+	thread::sleep(Duration::from_millis(STOP_POLL_INTERVAL_MS));
+	if unsafe { libc::kill(pid, 0) } != 0 {
+		let _ = fs::remove_file(PID_FILE);
+		println!("Grin daemon (pid {}) killed.", pid);
+	} else {
+		error!("Grin daemon (pid {}) is still running after SIGKILL; leaving {} in place.", pid, PID_FILE);
+	}
+}
+
+/// Writes a fully-populated default `ServerConfig` to `~/.grin` as JSON, so
+/// users can see and edit every tunable instead of discovering fields by
+/// trial and error.
+fn write_default_config() {
+	let mut config_path = env::home_dir().ok_or("Failed to detect home directory!").unwrap();
+	config_path.push(GRIN_HOME);
+
+	let json = serde_json::to_string_pretty(&default_config()).expect("Could not serialize default config.");
+	let mut file = File::create(&config_path)
+		.unwrap_or_else(|e| panic!("Could not write {}: {}", config_path.display(), e));
+	file.write_all(json.as_bytes()).expect("Could not write config file.");
+	println!("Wrote default configuration to {}.", config_path.display());
+}
+
+/// Opens the chain store backing `server_config`, without starting the rest
+/// of the server. Used by the `export`/`import` tooling commands, which
+/// only need read/write access to the block store.
+fn open_chain_store(server_config: &grin::ServerConfig) -> Arc<chain::ChainStore> {
+	Arc::new(chain::store::ChainKVStore::new(server_config.db_root.clone())
+		.expect("Could not open the chain store."))
+}
+
+/// Serializes every block from genesis to the current tip into `path`, one
+/// length-prefixed block per entry, so operators can back up or transplant
+/// a node's chain without re-syncing it from peers.
+fn export_chain(server_config: &grin::ServerConfig, path: &str) {
+	let chain_store = open_chain_store(server_config);
+	let tip = chain_store.head().expect("Could not read the chain tip.");
+
+	let mut file = File::create(path).unwrap_or_else(|e| panic!("Could not create {}: {}", path, e));
+	for height in 0..(tip.height + 1) {
+		let header = chain_store.get_header_by_height(height)
+			.unwrap_or_else(|e| panic!("Could not read header at height {}: {}", height, e));
+		let block = chain_store.get_block(&header.hash())
+			.unwrap_or_else(|e| panic!("Could not read block at height {}: {}", height, e));
+
+		let mut bytes = Vec::new();
+		core::ser::serialize(&mut bytes, &block).expect("Could not serialize block.");
+		write_u64(&mut file, bytes.len() as u64).expect("Could not write to export file.");
+		file.write_all(&bytes).expect("Could not write to export file.");
+	}
+	info!("Exported {} blocks to {}.", tip.height + 1, path);
+}
+
+/// Reads blocks previously written by `export_chain` and replays them
+/// through the normal chain pipeline, in order, skipping blocks already
+/// known to the store and halting at the first one that fails validation.
+///
+/// CAVEAT: this drives `chain::pipe::process_block` directly against a bare
+/// `ChainStore`, not the `BlockContext` (head, sumtrees, orphan handling)
+/// that a running `chain::Chain` builds around it. Constructing a real
+/// `Chain` here needs the genesis block, `ChainAdapter` and PoW-verifier
+/// wiring that only `grin::Server`'s startup owns, so this standalone tool
+/// cannot do it without that code. Don't treat a completed import as a
+/// verified restore: the hard failure below only catches the tip ending
+/// short of the last imported block's height, not a UTXO/sumtree state that's
+/// silently wrong. The warning below is printed on every run, not just
+/// documented here, so this can't be missed by someone who didn't read
+/// the source. Confirm by running `server run` against the imported
+/// store and checking it syncs cleanly with peers.
+fn import_chain(server_config: &grin::ServerConfig, path: &str) {
+	error!("import_chain does not rebuild sumtrees or UTXO state (see the \
+	        CAVEAT on import_chain in main.rs); confirm the result by \
+	        running 'server run' against {} and checking it syncs cleanly \
+	        with peers before relying on it.", server_config.db_root);
+	let chain_store = open_chain_store(server_config);
+	let mut file = File::open(path).unwrap_or_else(|e| panic!("Could not open {}: {}", path, e));
+
+	let mut height = 0u64;
+	loop {
+		let len = match read_u64(&mut file) {
+			Ok(len) => len,
+			Err(_) => break,
+		};
+		let mut bytes = vec![0; len as usize];
+		file.read_exact(&mut bytes).expect("Truncated export file.");
+		let block: core::core::Block = core::ser::deserialize(&mut &bytes[..])
+			.unwrap_or_else(|e| panic!("Could not deserialize block at height {}: {:?}", height, e));
+
+		match chain_store.get_block(&block.hash()) {
+			// Already have this block, nothing to replay.
+			Ok(_) => (),
+			Err(chain::types::Error::NotFoundErr) => {
+				if let Err(e) = chain::pipe::process_block(&block, chain_store.clone(), chain::pipe::Options::NONE) {
+					error!("Import failed at height {}: {:?}", height, e);
+					return;
+				}
+			}
+			Err(e) => {
+				error!("Could not check store for block at height {}: {:?}", height, e);
+				return;
+			}
+		}
+		height += 1;
+	}
+
+	match chain_store.head() {
+		Ok(tip) if height == 0 || tip.height + 1 >= height => {
+			info!("Imported {} blocks from {}, tip now at height {}.", height, path, tip.height);
+		}
+		Ok(tip) => {
+			panic!("Imported {} blocks from {} but the chain tip only reached height {}; \
+			        the pipeline did not advance correctly.",
+			       height, path, tip.height);
+		}
+		Err(e) => panic!("Imported {} blocks from {} but could not read the resulting tip: {:?}", height, path, e),
+	}
+}
+
+fn write_u64<W: Write>(w: &mut W, n: u64) -> io::Result<()> {
+	let bytes = [(n >> 56) as u8, (n >> 48) as u8, (n >> 40) as u8, (n >> 32) as u8,
+	             (n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8];
+	w.write_all(&bytes)
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+	let mut bytes = [0u8; 8];
+	r.read_exact(&mut bytes)?;
+	Ok((bytes[0] as u64) << 56 | (bytes[1] as u64) << 48 | (bytes[2] as u64) << 40 | (bytes[3] as u64) << 32 |
+	   (bytes[4] as u64) << 24 | (bytes[5] as u64) << 16 | (bytes[6] as u64) << 8 | (bytes[7] as u64))
+}
+
+/// Queries the running Grin node's `/v1/chain` endpoint and prints the
+/// current tip, either as a human-readable summary or as raw JSON.
+fn client_status_command(status_args: &ArgMatches) {
+	let server_config = read_config();
+	let url = format!("http://{}/v1/chain", server_config.api_http_addr);
+
+	let tip: api::ChainTip = api::get(&url[..]).unwrap_or_else(|e| {
+		panic!("Failed to reach Grin node at {}: {}", url, e);
+	});
+
+	if status_args.is_present("json") {
+		println!("{}", serde_json::to_string(&tip).unwrap());
+	} else {
+		println!("Height: {}", tip.height);
+		println!("Total difficulty: {}", tip.total_difficulty);
+		println!("Head hash: {}", tip.last_block_h);
+	}
+}
+
+fn wallet_command(wallet_args: &ArgMatches) {
+	// "init" doesn't operate on an existing seed, handle it before we
+	// require a mnemonic to be present.
+	if let ("init", Some(init_args)) = wallet_args.subcommand() {
+		let entropy_bits = init_args.value_of("entropy")
+			.map(|e| e.parse().expect("Could not parse entropy as a whole number."))
+			.unwrap_or(128);
+		let phrase = mnemonic::generate(entropy_bits)
+			.unwrap_or_else(|e| panic!("Could not generate mnemonic: {}", e));
+		println!("{}", phrase);
+		return;
+	}
+
+	let phrase = wallet_args.value_of("mnemonic")
+		.expect("Wallet mnemonic required, see 'grin wallet init'.");
+	let passphrase = wallet_args.value_of("passphrase").unwrap_or("");
+	let seed = mnemonic::mnemonic_to_seed(phrase, passphrase)
+		.unwrap_or_else(|e| panic!("Invalid mnemonic: {}", e));
+
+	let s = Secp256k1::new();
+	let key = wallet::ExtendedKey::from_seed(&s, &seed[..])
+		.expect("Error deriving extended key from seed.");
+
+	match wallet_args.subcommand() {
+		("receive", Some(receive_args)) => {
+			match receive_args.subcommand() {
+				("request", Some(request_args)) => {
+					let amount = request_args.value_of("amount")
+						.expect("Amount to request required")
+						.parse()
+						.expect("Could not parse amount as a whole number.");
+					let pubkey = PublicKey::from_secret_key(&s, &key.key)
+						.expect("Could not derive public key from wallet seed.");
+					let request = payment_request::PaymentRequest {
+						amount: amount,
+						endpoint: WALLET_RECEIVE_ADDR.to_string(),
+						pubkey: pubkey,
+					};
+					println!("{}", payment_request::encode(&request, &s));
+				}
+				_ => {
+					info!("Starting the Grin wallet receiving daemon...");
+					let mut apis = api::ApiServer::new("/v1".to_string());
+					apis.register_endpoint("/receive_coinbase".to_string(),
+					                       wallet::WalletReceiver { key: key });
+					apis.start(WALLET_RECEIVE_ADDR).unwrap_or_else(|e| {
+						error!("Failed to start Grin wallet receiver: {}.", e);
+					});
+				}
+			}
+		}
+		("send", Some(send_args)) => {
+			let mut amount = send_args.value_of("amount")
+				.map(|a| a.parse().expect("Could not parse amount as a whole number."));
+			let mut dest = "stdout".to_string();
+
+			if let Some(d) = send_args.value_of("dest") {
+				if payment_request::looks_like_request(d) {
+					// Don't fall through to treating this as a plain
+					// address: a mistyped or truncated request should
+					// error loudly rather than silently send to whatever
+					// `d` happens to parse as.
+					let request = payment_request::decode(d, &s)
+						.unwrap_or_else(|e| panic!("Invalid payment request {:?}: {:?}", d, e));
+					info!("Decoded payment request for {} to {}.", request.amount, request.endpoint);
+					amount = Some(request.amount);
+					dest = request.endpoint;
+				} else {
+					dest = d.to_string();
+				}
+			}
+
+			let amount = amount.expect("Amount to send required");
+			wallet::issue_send_tx(&key, amount, dest).unwrap();
+		}
+		_ => panic!("Unknown wallet command, use 'grin help wallet' for details"),
+	}
+}
+
+fn read_config() -> grin::ServerConfig {
+	let mut config_path = env::home_dir().ok_or("Failed to detect home directory!").unwrap();
+	config_path.push(GRIN_HOME);
+	if !config_path.exists() {
+		return default_config();
+	}
+	let mut config_file = File::open(config_path).unwrap();
+	let mut config_content = String::new();
+	config_file.read_to_string(&mut config_content).unwrap();
+	serde_json::from_str(config_content.as_str()).unwrap()
+}
+
+fn default_config() -> grin::ServerConfig {
+	grin::ServerConfig {
+		cuckoo_size: 12,
+		seeding_type: grin::Seeding::WebStatic,
+		api_http_addr: "127.0.0.1:13415".to_string(),
+		..Default::default()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// Covers the length-prefix framing `export_chain`/`import_chain` rely
+	// on; exercising the full export->import round trip needs a real
+	// `chain::Chain` fixture, which isn't available to this standalone
+	// binary (see the CAVEAT on `import_chain`).
+	#[test]
+	fn u64_roundtrip() {
+		let mut buf = Vec::new();
+		for &n in &[0u64, 1, 255, 256, u32::max_value() as u64, u64::max_value()] {
+			buf.clear();
+			write_u64(&mut buf, n).unwrap();
+			assert_eq!(buf.len(), 8);
+			assert_eq!(read_u64(&mut &buf[..]).unwrap(), n);
+		}
+	}
+
+	#[test]
+	fn u64_big_endian_byte_order() {
+		let mut buf = Vec::new();
+		write_u64(&mut buf, 0x0102030405060708).unwrap();
+		assert_eq!(buf, vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+	}
+}
@@ -0,0 +1,228 @@
+// Copyright 2016 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! BIP39 mnemonic generation and seed derivation for the wallet CLI.
+//!
+//! Replaces the old single-round Keccak hash of the passphrase with the
+//! standard entropy -> mnemonic -> PBKDF2 seed pipeline, so wallet backups
+//! are portable 12-24 word phrases instead of an ad-hoc secret.
+
+use std::fmt;
+
+use pbkdf2::pbkdf2;
+use hmac::Hmac;
+use sha2::{Digest, Sha256, Sha512};
+use rand::{OsRng, Rng};
+
+/// The BIP39 English wordlist, 2048 entries, one per line.
+const WORDLIST_TEXT: &'static str = include_str!("wordlist_english.txt");
+
+const PBKDF2_ROUNDS: u32 = 2048;
+const SEED_LEN: usize = 64;
+
+#[derive(Debug)]
+pub enum MnemonicError {
+	/// Requested entropy length isn't a valid BIP39 size (128-256 bits, multiple of 32).
+	InvalidEntropyLength(usize),
+	/// The phrase doesn't have a word count BIP39 produces (12, 15, 18, 21 or 24).
+	InvalidWordCount(usize),
+	/// A word in the phrase isn't part of the wordlist.
+	UnknownWord(String),
+	/// The embedded checksum didn't match the recomputed one.
+	InvalidChecksum,
+}
+
+impl fmt::Display for MnemonicError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			MnemonicError::InvalidEntropyLength(bits) => {
+				write!(f, "{} is not a valid entropy length, must be 128-256 bits in steps of 32", bits)
+			}
+			MnemonicError::InvalidWordCount(count) => {
+				write!(f, "{} is not a valid mnemonic word count, expected 12, 15, 18, 21 or 24", count)
+			}
+			MnemonicError::UnknownWord(ref word) => write!(f, "\"{}\" is not in the BIP39 wordlist", word),
+			MnemonicError::InvalidChecksum => write!(f, "mnemonic checksum does not match, phrase may be mistyped"),
+		}
+	}
+}
+
+fn wordlist() -> Vec<&'static str> {
+	WORDLIST_TEXT.lines().collect()
+}
+
+/// Generates a new mnemonic phrase from `entropy_bits` bits of fresh
+/// randomness (128, 160, 192, 224 or 256).
+pub fn generate(entropy_bits: usize) -> Result<String, MnemonicError> {
+	if entropy_bits < 128 || entropy_bits > 256 || entropy_bits % 32 != 0 {
+		return Err(MnemonicError::InvalidEntropyLength(entropy_bits));
+	}
+	let mut entropy = vec![0u8; entropy_bits / 8];
+	let mut rng = OsRng::new().expect("Failed to access system RNG.");
+	rng.fill_bytes(&mut entropy);
+	entropy_to_mnemonic(&entropy)
+}
+
+/// Turns raw entropy into its mnemonic representation by appending a
+/// checksum (the leading `entropy_bits/32` bits of `SHA256(entropy)`) and
+/// mapping each resulting 11-bit group onto a wordlist entry.
+fn entropy_to_mnemonic(entropy: &[u8]) -> Result<String, MnemonicError> {
+	let entropy_bits = entropy.len() * 8;
+	if entropy_bits < 128 || entropy_bits > 256 || entropy_bits % 32 != 0 {
+		return Err(MnemonicError::InvalidEntropyLength(entropy_bits));
+	}
+	let checksum_bits = entropy_bits / 32;
+
+	let mut hasher = Sha256::default();
+	hasher.input(entropy);
+	let hash = hasher.result();
+
+	let mut bits = bytes_to_bits(entropy);
+	bits.extend(bytes_to_bits(&hash).into_iter().take(checksum_bits));
+
+	let words = wordlist();
+	let phrase = bits.chunks(11)
+		.map(|group| words[bits_to_index(group)])
+		.collect::<Vec<_>>()
+		.join(" ");
+	Ok(phrase)
+}
+
+/// Validates `phrase`'s checksum and recovers the original entropy.
+fn mnemonic_to_entropy(phrase: &str) -> Result<Vec<u8>, MnemonicError> {
+	let words = wordlist();
+	let phrase_words: Vec<&str> = phrase.split_whitespace().collect();
+	match phrase_words.len() {
+		12 | 15 | 18 | 21 | 24 => (),
+		count => return Err(MnemonicError::InvalidWordCount(count)),
+	}
+
+	let mut bits = Vec::with_capacity(phrase_words.len() * 11);
+	for word in &phrase_words {
+		let index = words.iter()
+			.position(|w| w == word)
+			.ok_or_else(|| MnemonicError::UnknownWord(word.to_string()))?;
+		bits.extend(index_to_bits(index));
+	}
+
+	let checksum_bits = bits.len() / 33;
+	let entropy_bits = bits.len() - checksum_bits;
+	let entropy = bits_to_bytes(&bits[..entropy_bits]);
+
+	let mut hasher = Sha256::default();
+	hasher.input(&entropy);
+	let hash = hasher.result();
+	let expected_checksum = &bytes_to_bits(&hash)[..checksum_bits];
+
+	if &bits[entropy_bits..] != expected_checksum {
+		return Err(MnemonicError::InvalidChecksum);
+	}
+	Ok(entropy)
+}
+
+/// Validates `phrase` and derives the 64-byte seed used to build the
+/// wallet's extended key, following BIP39's PBKDF2-HMAC-SHA512 derivation.
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<[u8; SEED_LEN], MnemonicError> {
+	mnemonic_to_entropy(phrase)?;
+
+	let salt = format!("mnemonic{}", passphrase);
+	let mut seed = [0u8; SEED_LEN];
+	pbkdf2::<Hmac<Sha512>>(phrase.as_bytes(), salt.as_bytes(), PBKDF2_ROUNDS as usize, &mut seed);
+	Ok(seed)
+}
+
+fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
+	let mut bits = Vec::with_capacity(bytes.len() * 8);
+	for byte in bytes {
+		for i in (0..8).rev() {
+			bits.push((byte >> i) & 1 == 1);
+		}
+	}
+	bits
+}
+
+fn bits_to_bytes(bits: &[bool]) -> Vec<u8> {
+	bits.chunks(8)
+		.map(|chunk| chunk.iter().fold(0u8, |acc, &bit| (acc << 1) | (bit as u8)))
+		.collect()
+}
+
+fn bits_to_index(bits: &[bool]) -> usize {
+	bits.iter().fold(0usize, |acc, &bit| (acc << 1) | (bit as usize))
+}
+
+fn index_to_bits(index: usize) -> Vec<bool> {
+	(0..11).rev().map(|i| (index >> i) & 1 == 1).collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// BIP39 trezor test vector: 128 bits of zero entropy, passphrase "TREZOR".
+	const ZERO_ENTROPY_MNEMONIC: &'static str =
+		"abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+	const ZERO_ENTROPY_SEED: &'static str =
+		"c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf14\
+		 1630c7a3c4ab7c81b2f001698e7463b04";
+
+	#[test]
+	fn zero_entropy_matches_bip39_vector() {
+		let mnemonic = entropy_to_mnemonic(&[0u8; 16]).unwrap();
+		assert_eq!(mnemonic, ZERO_ENTROPY_MNEMONIC);
+	}
+
+	#[test]
+	fn zero_entropy_seed_matches_bip39_vector() {
+		let seed = mnemonic_to_seed(ZERO_ENTROPY_MNEMONIC, "TREZOR").unwrap();
+		let expected = util_hex_decode(ZERO_ENTROPY_SEED);
+		assert_eq!(&seed[..], &expected[..]);
+	}
+
+	#[test]
+	fn entropy_mnemonic_roundtrip() {
+		for entropy_bits in &[128usize, 160, 192, 224, 256] {
+			let entropy: Vec<u8> = (0..entropy_bits / 8).map(|i| i as u8).collect();
+			let mnemonic = entropy_to_mnemonic(&entropy).unwrap();
+			let recovered = mnemonic_to_entropy(&mnemonic).unwrap();
+			assert_eq!(recovered, entropy);
+		}
+	}
+
+	#[test]
+	fn rejects_tampered_checksum() {
+		let mnemonic = entropy_to_mnemonic(&[0u8; 16]).unwrap();
+		let mut words: Vec<&str> = mnemonic.split_whitespace().collect();
+		// Swap the last word for another valid wordlist entry. "zoo" also sets
+		// the last 7 entropy bits, and its checksum bits (1111) differ from
+		// the checksum of that altered entropy, so the phrase must be rejected.
+		*words.last_mut().unwrap() = "zoo";
+		let tampered = words.join(" ");
+		match mnemonic_to_entropy(&tampered) {
+			Err(MnemonicError::InvalidChecksum) => (),
+			other => panic!("expected InvalidChecksum, got {:?}", other),
+		}
+	}
+
+	fn util_hex_decode(s: &str) -> Vec<u8> {
+		let bytes = s.as_bytes();
+		let mut out = Vec::with_capacity(bytes.len() / 2);
+		let mut i = 0;
+		while i < bytes.len() {
+			out.push(u8::from_str_radix(&s[i..i + 2], 16).unwrap());
+			i += 2;
+		}
+		out
+	}
+}
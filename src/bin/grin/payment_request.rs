@@ -0,0 +1,307 @@
+// Copyright 2016 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compact, copyable payment-request strings for wallet send/receive.
+//!
+//! Bundles an amount, the receiver's public key and the endpoint to
+//! contact into a single bech32-encoded token, so a sender only needs one
+//! string instead of juggling raw amounts and ports out-of-band.
+
+use secp::key::PublicKey;
+use secp::Secp256k1;
+
+/// Human-readable prefix for every Grin payment request.
+const HRP: &'static str = "grin";
+const CHARSET: &'static [u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const CHECKSUM_LEN: usize = 6;
+
+#[derive(Debug)]
+pub enum RequestError {
+	InvalidChecksum,
+	InvalidHrp,
+	InvalidChar(char),
+	Malformed,
+}
+
+/// A decoded payment request: how much to pay, to which public key, and
+/// where to send the resulting transaction.
+#[derive(Debug)]
+pub struct PaymentRequest {
+	pub amount: u64,
+	pub endpoint: String,
+	pub pubkey: PublicKey,
+}
+
+/// Encodes `request` as a bech32 string prefixed with `grin1`.
+pub fn encode(request: &PaymentRequest, secp: &Secp256k1) -> String {
+	let mut data = Vec::new();
+	data.extend_from_slice(&u64_to_be_bytes(request.amount));
+
+	let endpoint_bytes = request.endpoint.as_bytes();
+	data.push(endpoint_bytes.len() as u8);
+	data.extend_from_slice(endpoint_bytes);
+
+	data.extend_from_slice(&request.pubkey.serialize_vec(secp, true));
+
+	let five_bit = convert_bits(&data, 8, 5, true).expect("Padding conversion cannot fail.");
+	bech32_encode(HRP, &five_bit)
+}
+
+/// Whether `s` carries the `grin1...` prefix `encode` produces, i.e.
+/// whether it's meant to be a payment request rather than a plain server
+/// address. Lets callers tell "this is a corrupted request" apart from
+/// "this was never a request to begin with".
+pub fn looks_like_request(s: &str) -> bool {
+	s.to_lowercase().starts_with(&format!("{}1", HRP))
+}
+
+/// Decodes a bech32 payment-request string produced by `encode`.
+pub fn decode(s: &str, secp: &Secp256k1) -> Result<PaymentRequest, RequestError> {
+	let (hrp, five_bit) = bech32_decode(s)?;
+	if hrp != HRP {
+		return Err(RequestError::InvalidHrp);
+	}
+	let data = convert_bits(&five_bit, 5, 8, false).ok_or(RequestError::Malformed)?;
+	if data.len() < 9 {
+		return Err(RequestError::Malformed);
+	}
+
+	let mut amount_bytes = [0u8; 8];
+	amount_bytes.copy_from_slice(&data[0..8]);
+	let amount = u64_from_be_bytes(amount_bytes);
+
+	let endpoint_len = data[8] as usize;
+	if data.len() != 9 + endpoint_len + 33 {
+		return Err(RequestError::Malformed);
+	}
+	let endpoint = String::from_utf8(data[9..9 + endpoint_len].to_vec())
+		.map_err(|_| RequestError::Malformed)?;
+	let pubkey = PublicKey::from_slice(secp, &data[9 + endpoint_len..])
+		.map_err(|_| RequestError::Malformed)?;
+
+	Ok(PaymentRequest { amount: amount, endpoint: endpoint, pubkey: pubkey })
+}
+
+fn u64_to_be_bytes(n: u64) -> [u8; 8] {
+	[(n >> 56) as u8, (n >> 48) as u8, (n >> 40) as u8, (n >> 32) as u8,
+	 (n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]
+}
+
+fn u64_from_be_bytes(bytes: [u8; 8]) -> u64 {
+	(bytes[0] as u64) << 56 | (bytes[1] as u64) << 48 | (bytes[2] as u64) << 40 | (bytes[3] as u64) << 32 |
+	(bytes[4] as u64) << 24 | (bytes[5] as u64) << 16 | (bytes[6] as u64) << 8 | (bytes[7] as u64)
+}
+
+// Bech32 (BIP173) encoding below: a 5-bit alphabet with a BCH-style
+// checksum, chosen so a mistyped or truncated payment request is rejected
+// instead of silently sending to the wrong place.
+
+fn polymod(values: &[u8]) -> u32 {
+	let gen = [0x3b6a57b2u32, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+	let mut chk = 1u32;
+	for &v in values {
+		let top = chk >> 25;
+		chk = ((chk & 0x1ffffff) << 5) ^ (v as u32);
+		for i in 0..5 {
+			if (top >> i) & 1 == 1 {
+				chk ^= gen[i];
+			}
+		}
+	}
+	chk
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+	let mut v: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+	v.push(0);
+	v.extend(hrp.bytes().map(|b| b & 31));
+	v
+}
+
+fn create_checksum(hrp: &str, data: &[u8]) -> Vec<u8> {
+	let mut values = hrp_expand(hrp);
+	values.extend_from_slice(data);
+	values.extend_from_slice(&[0u8; CHECKSUM_LEN]);
+	let pm = polymod(&values) ^ 1;
+	(0..CHECKSUM_LEN).map(|i| ((pm >> (5 * (CHECKSUM_LEN - 1 - i))) & 31) as u8).collect()
+}
+
+fn verify_checksum(hrp: &str, data: &[u8]) -> bool {
+	let mut values = hrp_expand(hrp);
+	values.extend_from_slice(data);
+	polymod(&values) == 1
+}
+
+fn bech32_encode(hrp: &str, data: &[u8]) -> String {
+	let checksum = create_checksum(hrp, data);
+	let mut result = String::from(hrp);
+	result.push('1');
+	for &d in data.iter().chain(checksum.iter()) {
+		result.push(CHARSET[d as usize] as char);
+	}
+	result
+}
+
+fn bech32_decode(s: &str) -> Result<(String, Vec<u8>), RequestError> {
+	let s = s.to_lowercase();
+	let pos = s.rfind('1').ok_or(RequestError::Malformed)?;
+	let hrp = s[..pos].to_string();
+	let data_part = &s[pos + 1..];
+	if data_part.len() < CHECKSUM_LEN {
+		return Err(RequestError::Malformed);
+	}
+
+	let mut data = Vec::with_capacity(data_part.len());
+	for c in data_part.chars() {
+		let v = CHARSET.iter()
+			.position(|&x| x as char == c)
+			.ok_or_else(|| RequestError::InvalidChar(c))? as u8;
+		data.push(v);
+	}
+	if !verify_checksum(&hrp, &data) {
+		return Err(RequestError::InvalidChecksum);
+	}
+	let payload_len = data.len() - CHECKSUM_LEN;
+	data.truncate(payload_len);
+	Ok((hrp, data))
+}
+
+/// Regroups `data` from `from_bits`-wide chunks into `to_bits`-wide chunks,
+/// as used to go between 8-bit payload bytes and bech32's 5-bit alphabet.
+fn convert_bits(data: &[u8], from_bits: u32, to_bits: u32, pad: bool) -> Option<Vec<u8>> {
+	let mut acc: u32 = 0;
+	let mut bits: u32 = 0;
+	let maxv = (1u32 << to_bits) - 1;
+	let mut ret = Vec::new();
+	for &value in data {
+		if (value as u32) >> from_bits != 0 {
+			return None;
+		}
+		acc = (acc << from_bits) | value as u32;
+		bits += from_bits;
+		while bits >= to_bits {
+			bits -= to_bits;
+			ret.push(((acc >> bits) & maxv) as u8);
+		}
+	}
+	if pad {
+		if bits > 0 {
+			ret.push(((acc << (to_bits - bits)) & maxv) as u8);
+		}
+	} else if bits >= from_bits || ((acc << (to_bits - bits)) & maxv) != 0 {
+		return None;
+	}
+	Some(ret)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use secp::key::SecretKey;
+
+	fn test_pubkey(secp: &Secp256k1) -> PublicKey {
+		let sk = SecretKey::from_slice(secp, &[1u8; 32]).unwrap();
+		PublicKey::from_secret_key(secp, &sk).unwrap()
+	}
+
+	#[test]
+	fn encode_decode_roundtrip() {
+		let secp = Secp256k1::new();
+		let pubkey = test_pubkey(&secp);
+		let request = PaymentRequest {
+			amount: 424242,
+			endpoint: "127.0.0.1:13416".to_string(),
+			pubkey: pubkey,
+		};
+
+		let encoded = encode(&request, &secp);
+		assert!(encoded.starts_with("grin1"));
+
+		let decoded = decode(&encoded, &secp).unwrap();
+		assert_eq!(decoded.amount, request.amount);
+		assert_eq!(decoded.endpoint, request.endpoint);
+		assert_eq!(decoded.pubkey.serialize_vec(&secp, true),
+		           request.pubkey.serialize_vec(&secp, true));
+	}
+
+	#[test]
+	fn rejects_flipped_character() {
+		let secp = Secp256k1::new();
+		let request = PaymentRequest {
+			amount: 1,
+			endpoint: "127.0.0.1:13416".to_string(),
+			pubkey: test_pubkey(&secp),
+		};
+		let encoded = encode(&request, &secp);
+
+		// Flip one data character near the end, leaving the checksum itself
+		// untouched, so this only passes if the checksum actually catches it.
+		let mut chars: Vec<char> = encoded.chars().collect();
+		let flip_at = chars.len() - CHECKSUM_LEN - 1;
+		let current = CHARSET.iter().position(|&c| c as char == chars[flip_at]).unwrap();
+		chars[flip_at] = CHARSET[(current + 1) % CHARSET.len()] as char;
+		let tampered: String = chars.into_iter().collect();
+
+		match decode(&tampered, &secp) {
+			Err(RequestError::InvalidChecksum) => (),
+			other => panic!("expected InvalidChecksum, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn rejects_wrong_hrp() {
+		let secp = Secp256k1::new();
+		let request = PaymentRequest {
+			amount: 1,
+			endpoint: "127.0.0.1:13416".to_string(),
+			pubkey: test_pubkey(&secp),
+		};
+
+		let mut data = Vec::new();
+		data.extend_from_slice(&u64_to_be_bytes(request.amount));
+		let endpoint_bytes = request.endpoint.as_bytes();
+		data.push(endpoint_bytes.len() as u8);
+		data.extend_from_slice(endpoint_bytes);
+		data.extend_from_slice(&request.pubkey.serialize_vec(&secp, true));
+		let five_bit = convert_bits(&data, 8, 5, true).unwrap();
+
+		// Valid bech32 (correct checksum for its own hrp), but not "grin".
+		let wrongly_addressed = bech32_encode("btc", &five_bit);
+		match decode(&wrongly_addressed, &secp) {
+			Err(RequestError::InvalidHrp) => (),
+			other => panic!("expected InvalidHrp, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn looks_like_request_distinguishes_requests_from_addresses() {
+		let secp = Secp256k1::new();
+		let request = PaymentRequest {
+			amount: 1,
+			endpoint: "127.0.0.1:13416".to_string(),
+			pubkey: test_pubkey(&secp),
+		};
+		let encoded = encode(&request, &secp);
+
+		assert!(looks_like_request(&encoded));
+		// A flipped character still reads as "was meant to be a request".
+		let mut chars: Vec<char> = encoded.chars().collect();
+		let flip_at = chars.len() - 1;
+		chars[flip_at] = if chars[flip_at] == 'q' { 'p' } else { 'q' };
+		assert!(looks_like_request(&chars.into_iter().collect::<String>()));
+
+		assert!(!looks_like_request("127.0.0.1:13416"));
+		assert!(!looks_like_request("stdout"));
+	}
+}